@@ -0,0 +1,42 @@
+use std::io;
+use std::mem::MaybeUninit;
+
+use x11::xlib::{XEvent, XNextEvent};
+use x11::xrandr::{
+    RRCrtcChangeNotifyMask, RROutputChangeNotifyMask, RRScreenChangeNotifyMask,
+    XRRQueryExtension, XRRSelectInput, XRRUpdateConfiguration,
+};
+
+use crate::DisplayInfo;
+
+/// Run forever, re-applying `policy` on every screen/CRTC/output change
+/// RandR reports (monitor plug/unplug, dock/undock, mode changes made by
+/// another client).
+///
+/// There is no `--once`-style escape hatch here by design: this is meant
+/// to replace a manual re-run of the tool after every hotplug, not to be
+/// mixed with one-shot usage. For the same reason, a `policy` failure on
+/// one event (e.g. a brief window during hotplug where no mode matches
+/// yet) is logged and the loop keeps running rather than killing the
+/// daemon — the whole point is not needing a manual restart.
+pub fn run(d: &DisplayInfo, policy: impl Fn(&DisplayInfo) -> io::Result<()>) -> io::Result<()> {
+    let mut event_base = 0;
+    let mut error_base = 0;
+    if unsafe { XRRQueryExtension(d.display, &mut event_base, &mut error_base) } == 0 {
+        return Err(io::ErrorKind::Unsupported.into());
+    }
+
+    let mask = RRScreenChangeNotifyMask | RRCrtcChangeNotifyMask | RROutputChangeNotifyMask;
+    unsafe { XRRSelectInput(d.display, d.root_window, mask) };
+
+    loop {
+        let mut event: XEvent = unsafe { MaybeUninit::zeroed().assume_init() };
+        unsafe { XNextEvent(d.display, &mut event) };
+
+        unsafe { XRRUpdateConfiguration(&mut event) };
+
+        if let Err(e) = policy(d) {
+            eprintln!("auto-display: failed to apply policy after hotplug: {e}");
+        }
+    }
+}
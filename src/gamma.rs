@@ -0,0 +1,162 @@
+use std::io;
+
+use x11::xrandr::{
+    RRCrtc, XRRAllocGamma, XRRCrtcGamma, XRRFreeGamma, XRRGetCrtcGammaSize, XRRSetCrtcGamma,
+};
+
+use crate::DisplayInfo;
+
+struct GammaRamp {
+    gamma: *mut XRRCrtcGamma,
+}
+
+impl GammaRamp {
+    fn alloc(size: i32) -> io::Result<GammaRamp> {
+        let gamma = unsafe { XRRAllocGamma(size) };
+        if gamma.is_null() {
+            Err(io::ErrorKind::OutOfMemory.into())
+        } else {
+            Ok(GammaRamp { gamma })
+        }
+    }
+}
+
+impl Drop for GammaRamp {
+    fn drop(&mut self) {
+        if !self.gamma.is_null() {
+            unsafe { XRRFreeGamma(self.gamma) };
+        }
+    }
+}
+
+/// Per-channel white-point multipliers for a blackbody approximation of
+/// `kelvin`.
+///
+/// At 6500K (daylight) all three channels are left untouched. Warmer
+/// temperatures (below 6500K) attenuate blue first, then green; cooler
+/// temperatures (above 6500K, up to 12000K) attenuate red first, then
+/// green, symmetrically. This is a coarse approximation, good enough for
+/// a redshift style display tint rather than colorimetric accuracy.
+fn white_point(kelvin: u32) -> (f64, f64, f64) {
+    let kelvin = kelvin.clamp(1000, 12000) as f64;
+
+    if kelvin <= 6500.0 {
+        let t = (kelvin - 1000.0) / (6500.0 - 1000.0);
+
+        let blue = t.powf(1.5).clamp(0.0, 1.0);
+        let green = (0.4 + 0.6 * t).clamp(0.0, 1.0);
+
+        (1.0, green, blue)
+    } else {
+        let t = (kelvin - 6500.0) / (12000.0 - 6500.0);
+
+        let red = (1.0 - t.powf(1.5)).clamp(0.0, 1.0);
+        let green = (1.0 - 0.6 * t).clamp(0.0, 1.0);
+
+        (red, green, 1.0)
+    }
+}
+
+fn size_of_ramp(d: &DisplayInfo, crtc: RRCrtc) -> io::Result<i32> {
+    let size = unsafe { XRRGetCrtcGammaSize(d.display, crtc) };
+    if size <= 0 {
+        Err(io::ErrorKind::NotFound.into())
+    } else {
+        Ok(size)
+    }
+}
+
+fn fill_ramp(ramp: &mut GammaRamp, size: i32, multipliers: (f64, f64, f64), brightness: f64) {
+    let (r_mul, g_mul, b_mul) = multipliers;
+    let brightness = brightness.clamp(0.0, 1.0);
+
+    for i in 0..size as isize {
+        let level = i as f64 / (size - 1).max(1) as f64;
+
+        let r = (level * r_mul * brightness).clamp(0.0, 1.0);
+        let g = (level * g_mul * brightness).clamp(0.0, 1.0);
+        let b = (level * b_mul * brightness).clamp(0.0, 1.0);
+
+        unsafe {
+            *(*ramp.gamma).red.offset(i) = (r * 65535.0) as u16;
+            *(*ramp.gamma).green.offset(i) = (g * 65535.0) as u16;
+            *(*ramp.gamma).blue.offset(i) = (b * 65535.0) as u16;
+        }
+    }
+}
+
+/// A gamma adjustment applied to a CRTC. Dropping it restores a linear,
+/// unadjusted ramp so the display doesn't stay tinted after the process
+/// exits.
+pub struct GammaAdjustment {
+    display: *mut x11::xlib::Display,
+    crtc: RRCrtc,
+    size: i32,
+}
+
+impl GammaAdjustment {
+    /// Apply `kelvin` (color temperature) and `brightness` (a `[0, 1]`
+    /// factor) to `crtc`'s gamma ramps.
+    pub fn apply(d: &DisplayInfo, crtc: RRCrtc, kelvin: u32, brightness: f64) -> io::Result<GammaAdjustment> {
+        let size = size_of_ramp(d, crtc)?;
+        let mut ramp = GammaRamp::alloc(size)?;
+
+        fill_ramp(&mut ramp, size, white_point(kelvin), brightness);
+
+        unsafe { XRRSetCrtcGamma(d.display, crtc, ramp.gamma) };
+
+        Ok(GammaAdjustment {
+            display: d.display,
+            crtc,
+            size,
+        })
+    }
+}
+
+impl Drop for GammaAdjustment {
+    fn drop(&mut self) {
+        let Ok(mut ramp) = GammaRamp::alloc(self.size) else {
+            return;
+        };
+
+        fill_ramp(&mut ramp, self.size, (1.0, 1.0, 1.0), 1.0);
+
+        unsafe { XRRSetCrtcGamma(self.display, self.crtc, ramp.gamma) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn white_point_is_neutral_at_daylight() {
+        assert_eq!(white_point(6500), (1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn white_point_attenuates_blue_and_green_below_daylight() {
+        assert_eq!(white_point(1000), (1.0, 0.4, 0.0));
+
+        let (r, g, b) = white_point(3000);
+        assert_eq!(r, 1.0);
+        assert!(g > 0.4 && g < 1.0);
+        assert!(b > 0.0 && b < 1.0);
+    }
+
+    #[test]
+    fn white_point_attenuates_red_and_green_above_daylight() {
+        assert_eq!(white_point(12000), (0.0, 0.4, 1.0));
+
+        let (r, g, b) = white_point(9000);
+        assert!(r > 0.0 && r < 1.0);
+        assert!(g > 0.4 && g < 1.0);
+        assert_eq!(b, 1.0);
+    }
+
+    #[test]
+    fn white_point_clamps_out_of_range_kelvin() {
+        assert_eq!(white_point(0), white_point(1000));
+        assert_eq!(white_point(u32::MAX), white_point(12000));
+    }
+}
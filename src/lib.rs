@@ -0,0 +1,551 @@
+//! Library surface for reading and changing the X11/XRandR display
+//! configuration. `src/main.rs` is a thin CLI built on top of this; other
+//! programs can depend on this crate directly instead of shelling out to
+//! `xrandr`.
+
+mod daemon;
+mod gamma;
+mod output;
+mod rotation;
+mod selection;
+
+pub use daemon::run as run_daemon;
+pub use gamma::GammaAdjustment;
+pub use output::OutputInfo;
+pub use rotation::{swapped_dimensions, validate_rotation, RotationSpec};
+pub use selection::{ExplicitMode, ModeFilter, Policy};
+
+use std::io;
+use std::os::raw::c_ulong;
+use std::ptr::null;
+
+use x11::xlib::{
+    CurrentTime, Display, XCloseDisplay, XDisplayHeight, XDisplayWidth, XOpenDisplay, XRootWindow,
+};
+use x11::xrandr::{
+    RRCrtc, RRMode, RROutput, RR_DoubleScan, RR_Interlace, XRRFreeScreenConfigInfo,
+    XRRFreeScreenResources, XRRGetScreenInfo, XRRGetScreenResources, XRRModeInfo, XRRRootToScreen,
+    XRRScreenConfiguration, XRRScreenResources, XRRSetScreenConfigAndRate, XRRSetScreenSize, XRRSizes,
+};
+
+#[derive(Debug)]
+pub struct DisplayInfo {
+    display: *mut Display,
+    root_window: c_ulong,
+}
+
+impl DisplayInfo {
+    fn from_primary() -> io::Result<DisplayInfo> {
+        let display = unsafe { XOpenDisplay(null()) };
+        if display.is_null() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        let root_window = unsafe { XRootWindow(display, 0) };
+        if root_window == 0 {
+            return Err(io::ErrorKind::Other.into());
+        }
+
+        if display.is_null() {
+            Err(io::ErrorKind::NotFound.into())
+        } else {
+            Ok(DisplayInfo {
+                display,
+                root_window,
+            })
+        }
+    }
+}
+
+impl Drop for DisplayInfo {
+    fn drop(&mut self) {
+        if !self.display.is_null() {
+            let res = unsafe { XCloseDisplay(self.display) };
+            if res != 0 {
+                eprintln!("Failed to drop display");
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ScreenInfo {
+    conf: *mut XRRScreenConfiguration,
+}
+
+impl ScreenInfo {
+    fn from_display(d: &DisplayInfo) -> io::Result<ScreenInfo> {
+        let conf = unsafe { XRRGetScreenInfo(d.display, d.root_window) };
+        if conf.is_null() {
+            Err(io::ErrorKind::NotFound.into())
+        } else {
+            Ok(ScreenInfo { conf })
+        }
+    }
+}
+
+impl Drop for ScreenInfo {
+    fn drop(&mut self) {
+        if !self.conf.is_null() {
+            unsafe {
+                XRRFreeScreenConfigInfo(self.conf);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ScreenResources {
+    res: *mut XRRScreenResources,
+}
+
+impl ScreenResources {
+    fn from_display(d: &DisplayInfo) -> io::Result<ScreenResources> {
+        let res = unsafe { XRRGetScreenResources(d.display, d.root_window) };
+        if res.is_null() {
+            Err(io::ErrorKind::NotFound.into())
+        } else {
+            Ok(ScreenResources {
+                res
+            })
+        }
+    }
+
+    fn num_modes(&self) -> usize {
+        if !self.res.is_null() {
+            unsafe { (*self.res).nmode as usize }
+        } else {
+            0
+        }
+    }
+
+    fn mode_info_get(&self, index: usize) -> io::Result<XRRModeInfo> {
+        if index >= self.num_modes() {
+            Err(io::ErrorKind::InvalidInput.into())
+        } else {
+            Ok(unsafe { *(*self.res).modes.add(index) })
+        }
+    }
+
+    fn num_outputs(&self) -> usize {
+        if !self.res.is_null() {
+            unsafe { (*self.res).noutput as usize }
+        } else {
+            0
+        }
+    }
+
+    fn output_id(&self, index: usize) -> io::Result<RROutput> {
+        if index >= self.num_outputs() {
+            Err(io::ErrorKind::InvalidInput.into())
+        } else {
+            Ok(unsafe { *(*self.res).outputs.add(index) })
+        }
+    }
+
+    fn num_crtcs(&self) -> usize {
+        if !self.res.is_null() {
+            unsafe { (*self.res).ncrtc as usize }
+        } else {
+            0
+        }
+    }
+
+    fn crtc_id(&self, index: usize) -> io::Result<RRCrtc> {
+        if index >= self.num_crtcs() {
+            Err(io::ErrorKind::InvalidInput.into())
+        } else {
+            Ok(unsafe { *(*self.res).crtcs.add(index) })
+        }
+    }
+}
+
+impl Drop for ScreenResources {
+    fn drop(&mut self) {
+        if !self.res.is_null() {
+            unsafe {
+                XRRFreeScreenResources(self.res);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ScreenSize {
+    width: u32,
+    height: u32,
+    // X11 size index reference needed by XRRSetScreenConfigAndRate
+    size_index: i32,
+}
+
+/// A concrete, applicable display mode: a size paired with one of its
+/// refresh rates.
+#[derive(Debug, Clone, Copy)]
+pub struct Mode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_hz: f64,
+    pub interlaced: bool,
+    size_index: i32,
+    mode_id: RRMode,
+}
+
+/// Vertical refresh in Hz, computed straight from the mode timings rather
+/// than matched positionally against `XRRRates`.
+///
+/// Interlaced modes pack two fields per frame (effectively halving the
+/// scanned `vTotal`) and double-scan modes repeat each line (effectively
+/// doubling it), so both flags have to be folded into `vTotal` before the
+/// division or the reported rate comes out wrong for exactly those modes.
+fn vertical_refresh_hz(mode_info: &XRRModeInfo) -> Option<f64> {
+    if mode_info.hTotal == 0 || mode_info.vTotal == 0 {
+        return None;
+    }
+
+    let mut v_total = mode_info.vTotal as f64;
+    if mode_info.modeFlags & RR_DoubleScan as c_ulong != 0 {
+        v_total *= 2.0;
+    }
+    if mode_info.modeFlags & RR_Interlace as c_ulong != 0 {
+        v_total /= 2.0;
+    }
+
+    Some(mode_info.dotClock as f64 / (mode_info.hTotal as f64 * v_total))
+}
+
+/// Whether two modes describe the same timings, just reported under
+/// different `RRMode` ids (this happens when the same mode is advertised
+/// by more than one output).
+fn modes_equal(a: &XRRModeInfo, b: &XRRModeInfo) -> bool {
+    a.width == b.width
+        && a.height == b.height
+        && a.dotClock / 10 == b.dotClock / 10
+        && a.hSyncStart == b.hSyncStart
+        && a.hSyncEnd == b.hSyncEnd
+        && a.hTotal == b.hTotal
+        && a.vSyncStart == b.vSyncStart
+        && a.vSyncEnd == b.vSyncEnd
+        && a.vTotal == b.vTotal
+}
+
+fn get_sizes(d: &DisplayInfo) -> io::Result<Vec<ScreenSize>> {
+    let mut num_sizes = 0;
+    let mut safe_sizes = Vec::new();
+
+    let screen = unsafe { XRRRootToScreen(d.display, d.root_window) };
+
+    let sizes = unsafe { XRRSizes(d.display, screen, &mut num_sizes) };
+    if sizes.is_null() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+
+    for i in 0..num_sizes {
+        let size = unsafe { *sizes.offset(i as isize) };
+
+        match (u32::try_from(size.width), u32::try_from(size.height)) {
+            (Ok(width), Ok(height)) => safe_sizes.push(ScreenSize {
+                width,
+                height,
+                size_index: i,
+            }),
+            _ => return Err(io::ErrorKind::Other.into()),
+        }
+    }
+
+    Ok(safe_sizes)
+}
+
+fn modes_for_size(d: &DisplayInfo, ssz: &ScreenSize) -> io::Result<Vec<Mode>> {
+    let screen_resources = ScreenResources::from_display(d)?;
+
+    let num_modes = screen_resources.num_modes();
+
+    let mut modes: Vec<Mode> = Vec::new();
+    let mut seen_modes: Vec<XRRModeInfo> = Vec::new();
+    for i in 0..num_modes {
+        let mode_info = screen_resources.mode_info_get(i)?;
+
+        if mode_info.width != ssz.width || mode_info.height != ssz.height {
+            continue;
+        }
+
+        if seen_modes.iter().any(|seen| modes_equal(seen, &mode_info)) {
+            continue;
+        }
+        seen_modes.push(mode_info);
+
+        let Some(refresh_hz) = vertical_refresh_hz(&mode_info) else {
+            continue;
+        };
+
+        #[cfg(debug_assertions)]
+        dbg!((mode_info.id, mode_info.width, mode_info.height, refresh_hz));
+
+        modes.push(Mode {
+            width: ssz.width,
+            height: ssz.height,
+            refresh_hz,
+            interlaced: mode_info.modeFlags & RR_Interlace as c_ulong != 0,
+            size_index: ssz.size_index,
+            mode_id: mode_info.id,
+        });
+    }
+
+    Ok(modes)
+}
+
+/// Open the primary (first) display. The returned handle owns the X11
+/// connection and closes it on drop.
+pub fn open_primary() -> io::Result<DisplayInfo> {
+    DisplayInfo::from_primary()
+}
+
+/// Enumerate the connected outputs, their driving CRTC and position.
+pub fn list_outputs(d: &DisplayInfo) -> io::Result<Vec<OutputInfo>> {
+    let res = ScreenResources::from_display(d)?;
+    output::list_outputs(d, &res)
+}
+
+/// Enumerate every `(size, refresh rate)` combination the primary screen
+/// reports as supported, across every size it knows about.
+pub fn list_modes(d: &DisplayInfo) -> io::Result<Vec<Mode>> {
+    let sizes = get_sizes(d)?;
+
+    let mut modes = Vec::new();
+    for size in &sizes {
+        modes.extend(modes_for_size(d, size)?);
+    }
+
+    Ok(modes)
+}
+
+/// The modes `output` itself advertises as supported (`output.modes`),
+/// for driving that output through its own CRTC (see
+/// [`apply_output_mode`]) rather than the whole screen.
+///
+/// Built directly from `ScreenResources`'s mode list rather than routed
+/// through [`list_modes`]: that path is keyed off the legacy
+/// `XRRSizes`-reported screen sizes, which on RandR-1.2 servers commonly
+/// reflect only one CRTC's modes, and would silently drop a secondary
+/// output's genuinely supported resolutions.
+///
+/// The returned `Mode`s have no meaningful `size_index` (there is no
+/// legacy screen size backing them) — pass them to
+/// [`apply_output_mode`], never [`apply_mode`].
+pub fn list_output_modes(d: &DisplayInfo, output: &OutputInfo) -> io::Result<Vec<Mode>> {
+    let res = ScreenResources::from_display(d)?;
+
+    let mut modes = Vec::new();
+    let mut seen_modes: Vec<XRRModeInfo> = Vec::new();
+    for i in 0..res.num_modes() {
+        let mode_info = res.mode_info_get(i)?;
+
+        if !output.modes.contains(&mode_info.id) {
+            continue;
+        }
+
+        if seen_modes.iter().any(|seen| modes_equal(seen, &mode_info)) {
+            continue;
+        }
+        seen_modes.push(mode_info);
+
+        let Some(refresh_hz) = vertical_refresh_hz(&mode_info) else {
+            continue;
+        };
+
+        modes.push(Mode {
+            width: mode_info.width,
+            height: mode_info.height,
+            refresh_hz,
+            interlaced: mode_info.modeFlags & RR_Interlace as c_ulong != 0,
+            size_index: -1,
+            mode_id: mode_info.id,
+        });
+    }
+
+    Ok(modes)
+}
+
+/// Apply `mode` at `rotation` as the screen's resolution, refresh rate
+/// and orientation.
+///
+/// `rotation` is validated against what the screen configuration reports
+/// as supported before anything is changed. Since `RR_Rotate_90`/`270`
+/// turn the screen on its side, the framebuffer itself is resized to
+/// match via [`XRRSetScreenSize`] ahead of the mode/rate/rotation change,
+/// using [`swapped_dimensions`] rather than the raw `mode` dimensions.
+///
+/// This drives the legacy whole-screen `XRRSetScreenConfigAndRate` path;
+/// see [`crate::OutputInfo`] and [`apply_output_mode`] for per-output
+/// CRTC control instead.
+pub fn apply_mode(d: &DisplayInfo, mode: &Mode, rotation: u16) -> io::Result<()> {
+    let screen_info = ScreenInfo::from_display(d)?;
+
+    rotation::validate_rotation(screen_info.conf, rotation)?;
+
+    let (fb_width, fb_height) = rotation::swapped_dimensions(mode.width, mode.height, rotation);
+
+    unsafe {
+        XRRSetScreenSize(d.display, d.root_window, fb_width as i32, fb_height as i32, 0, 0);
+
+        XRRSetScreenConfigAndRate(
+            d.display,
+            screen_info.conf,
+            d.root_window,
+            mode.size_index,
+            rotation,
+            mode.refresh_hz.round() as i16,
+            CurrentTime,
+        );
+    }
+
+    Ok(())
+}
+
+/// Apply `mode` at `rotation` to `output` through its own CRTC, leaving
+/// every other output untouched.
+///
+/// `output` must currently have a CRTC driving it (`output.crtc != 0`)
+/// and must advertise `mode` as one of its supported modes — use
+/// [`list_output_modes`] rather than [`list_modes`] to pick one, or this
+/// returns an error instead of guessing a substitute.
+///
+/// Like [`apply_mode`], the framebuffer is grown via [`XRRSetScreenSize`]
+/// before the CRTC is touched: [`swapped_dimensions`] gives this CRTC's
+/// footprint at `rotation`, and `output.x`/`output.y` account for it not
+/// necessarily sitting at the screen origin, so a rotated secondary
+/// output doesn't get clipped against a framebuffer sized for its
+/// unrotated bounds.
+pub fn apply_output_mode(
+    d: &DisplayInfo,
+    output: &OutputInfo,
+    mode: &Mode,
+    rotation: u16,
+) -> io::Result<()> {
+    if !output.modes.contains(&mode.mode_id) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("output {:?} does not support the requested mode", output.name),
+        ));
+    }
+
+    if output.crtc == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("output {:?} has no CRTC driving it", output.name),
+        ));
+    }
+
+    let screen_info = ScreenInfo::from_display(d)?;
+    rotation::validate_rotation(screen_info.conf, rotation)?;
+
+    let (crtc_width, crtc_height) = rotation::swapped_dimensions(mode.width, mode.height, rotation);
+    let (screen_width, screen_height) =
+        unsafe { (XDisplayWidth(d.display, 0), XDisplayHeight(d.display, 0)) };
+    let fb_width = (output.x.max(0) as u32 + crtc_width).max(screen_width.max(0) as u32);
+    let fb_height = (output.y.max(0) as u32 + crtc_height).max(screen_height.max(0) as u32);
+
+    let res = ScreenResources::from_display(d)?;
+
+    unsafe {
+        XRRSetScreenSize(d.display, d.root_window, fb_width as i32, fb_height as i32, 0, 0);
+    }
+
+    output::set_output_mode(
+        d,
+        &res,
+        output.output,
+        output.crtc,
+        mode.mode_id,
+        rotation,
+        output.x,
+        output.y,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode_info(
+        id: RRMode,
+        width: u32,
+        height: u32,
+        dot_clock: c_ulong,
+        h_total: u32,
+        v_total: u32,
+        mode_flags: c_ulong,
+    ) -> XRRModeInfo {
+        XRRModeInfo {
+            id,
+            width,
+            height,
+            dotClock: dot_clock,
+            hSyncStart: 0,
+            hSyncEnd: 0,
+            hTotal: h_total,
+            hSkew: 0,
+            vSyncStart: 0,
+            vSyncEnd: 0,
+            vTotal: v_total,
+            name: std::ptr::null_mut(),
+            nameLength: 0,
+            modeFlags: mode_flags,
+        }
+    }
+
+    #[test]
+    fn vertical_refresh_hz_plain_mode() {
+        // 1920x1080@60Hz-ish timings: dotClock / (hTotal * vTotal).
+        let info = mode_info(1, 1920, 1080, 148_500_000, 2200, 1125, 0);
+        let hz = vertical_refresh_hz(&info).unwrap();
+        assert!((hz - 60.0).abs() < 0.01, "expected ~60Hz, got {hz}");
+    }
+
+    #[test]
+    fn vertical_refresh_hz_interlace_halves_v_total() {
+        let progressive = mode_info(1, 720, 480, 13_500_000, 858, 525, 0);
+        let interlaced = mode_info(2, 720, 480, 13_500_000, 858, 525, RR_Interlace as c_ulong);
+
+        let progressive_hz = vertical_refresh_hz(&progressive).unwrap();
+        let interlaced_hz = vertical_refresh_hz(&interlaced).unwrap();
+
+        assert!((interlaced_hz - progressive_hz * 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn vertical_refresh_hz_double_scan_doubles_v_total() {
+        let plain = mode_info(1, 320, 240, 12_600_000, 400, 525, 0);
+        let double_scanned = mode_info(2, 320, 240, 12_600_000, 400, 525, RR_DoubleScan as c_ulong);
+
+        let plain_hz = vertical_refresh_hz(&plain).unwrap();
+        let double_scanned_hz = vertical_refresh_hz(&double_scanned).unwrap();
+
+        assert!((plain_hz - double_scanned_hz * 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn vertical_refresh_hz_rejects_zero_totals() {
+        let info = mode_info(1, 1920, 1080, 148_500_000, 0, 1125, 0);
+        assert_eq!(vertical_refresh_hz(&info), None);
+
+        let info = mode_info(1, 1920, 1080, 148_500_000, 2200, 0, 0);
+        assert_eq!(vertical_refresh_hz(&info), None);
+    }
+
+    #[test]
+    fn modes_equal_ignores_id_and_tolerates_dot_clock_rounding() {
+        let a = mode_info(1, 1920, 1080, 148_500_000, 2200, 1125, 0);
+        let b = mode_info(2, 1920, 1080, 148_500_004, 2200, 1125, 0);
+
+        assert!(modes_equal(&a, &b));
+    }
+
+    #[test]
+    fn modes_equal_rejects_different_timings() {
+        let a = mode_info(1, 1920, 1080, 148_500_000, 2200, 1125, 0);
+        let b = mode_info(2, 1920, 1080, 148_500_000, 2080, 1125, 0);
+
+        assert!(!modes_equal(&a, &b));
+    }
+}
@@ -0,0 +1,152 @@
+use std::io;
+use std::slice;
+
+use x11::xlib::CurrentTime;
+use x11::xrandr::{
+    RRCrtc, RRMode, RROutput, RR_Connected, XRRCrtcInfo, XRRFreeCrtcInfo, XRRFreeOutputInfo,
+    XRRGetCrtcInfo, XRRGetOutputInfo, XRROutputInfo, XRRSetCrtcConfig,
+};
+
+use crate::{DisplayInfo, ScreenResources};
+
+/// A connected output, resolved to its driving CRTC, current position and
+/// the modes it advertises as supported.
+#[derive(Debug)]
+pub struct OutputInfo {
+    pub output: RROutput,
+    pub name: String,
+    pub crtc: RRCrtc,
+    pub modes: Vec<RRMode>,
+    pub x: i32,
+    pub y: i32,
+}
+
+struct OutputReply {
+    info: *mut XRROutputInfo,
+}
+
+impl OutputReply {
+    fn get(d: &DisplayInfo, res: &ScreenResources, output: RROutput) -> io::Result<OutputReply> {
+        let info = unsafe { XRRGetOutputInfo(d.display, res.res, output) };
+        if info.is_null() {
+            Err(io::ErrorKind::NotFound.into())
+        } else {
+            Ok(OutputReply { info })
+        }
+    }
+}
+
+impl Drop for OutputReply {
+    fn drop(&mut self) {
+        if !self.info.is_null() {
+            unsafe { XRRFreeOutputInfo(self.info) };
+        }
+    }
+}
+
+struct CrtcReply {
+    info: *mut XRRCrtcInfo,
+}
+
+impl CrtcReply {
+    fn get(d: &DisplayInfo, res: &ScreenResources, crtc: RRCrtc) -> io::Result<CrtcReply> {
+        let info = unsafe { XRRGetCrtcInfo(d.display, res.res, crtc) };
+        if info.is_null() {
+            Err(io::ErrorKind::NotFound.into())
+        } else {
+            Ok(CrtcReply { info })
+        }
+    }
+}
+
+impl Drop for CrtcReply {
+    fn drop(&mut self) {
+        if !self.info.is_null() {
+            unsafe { XRRFreeCrtcInfo(self.info) };
+        }
+    }
+}
+
+/// Enumerate every connected output, mapping each to the CRTC currently
+/// driving it (if any) and the modes it reports as supported.
+///
+/// Disconnected and unknown-connection outputs are skipped: they have no
+/// meaningful CRTC or mode list to expose.
+pub fn list_outputs(d: &DisplayInfo, res: &ScreenResources) -> io::Result<Vec<OutputInfo>> {
+    let mut outputs = Vec::new();
+
+    for i in 0..res.num_outputs() {
+        let output = res.output_id(i)?;
+        let reply = OutputReply::get(d, res, output)?;
+        let info = unsafe { &*reply.info };
+
+        if info.connection != RR_Connected {
+            continue;
+        }
+
+        let name = unsafe { slice::from_raw_parts(info.name as *const u8, info.nameLen as usize) };
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        let modes = unsafe { slice::from_raw_parts(info.modes, info.nmode as usize) }.to_vec();
+
+        let (x, y) = if info.crtc != 0 {
+            let crtc = CrtcReply::get(d, res, info.crtc)?;
+            let crtc_info = unsafe { &*crtc.info };
+            (crtc_info.x, crtc_info.y)
+        } else {
+            (0, 0)
+        };
+
+        outputs.push(OutputInfo {
+            output,
+            name,
+            crtc: info.crtc,
+            modes,
+            x,
+            y,
+        });
+    }
+
+    Ok(outputs)
+}
+
+/// Drive `output` through `crtc` at `mode` and `rotation`, placed at
+/// `(x, y)` in the screen's coordinate space.
+///
+/// Callers should validate `rotation` against the screen's supported
+/// rotations (see [`crate::rotation::validate_rotation`]) before calling
+/// this, since an unsupported rotation fails the underlying request with
+/// BadMatch.
+pub fn set_output_mode(
+    d: &DisplayInfo,
+    res: &ScreenResources,
+    output: RROutput,
+    crtc: RRCrtc,
+    mode: RRMode,
+    rotation: u16,
+    x: i32,
+    y: i32,
+) -> io::Result<()> {
+    let mut outputs = [output];
+
+    let status = unsafe {
+        XRRSetCrtcConfig(
+            d.display,
+            res.res,
+            crtc,
+            CurrentTime,
+            x,
+            y,
+            mode,
+            rotation,
+            outputs.as_mut_ptr(),
+            1,
+        )
+    };
+
+    if status != 0 {
+        Err(io::ErrorKind::Other.into())
+    } else {
+        Ok(())
+    }
+}
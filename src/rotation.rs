@@ -0,0 +1,148 @@
+use std::io;
+use std::str::FromStr;
+
+use x11::xrandr::{
+    XRRConfigRotations, RR_Reflect_X, RR_Reflect_Y, RR_Rotate_0, RR_Rotate_180, RR_Rotate_270,
+    RR_Rotate_90, XRRScreenConfiguration,
+};
+
+/// A requested rotation/reflection, as typed on the command line: one of
+/// `0`, `90`, `180`, `270`, optionally suffixed with `+x`, `+y` or `+xy`
+/// to also reflect the named axis (e.g. `90+x`).
+///
+/// This wraps the same `RR_Rotate_*`/`RR_Reflect_*` bitmask the X11 calls
+/// take directly, so [`RotationSpec::bits`] can be passed straight to
+/// [`validate_rotation`] and the `XRRSetScreenConfigAndRate`/
+/// `XRRSetCrtcConfig` calls that use it.
+#[derive(Debug, Clone, Copy)]
+pub struct RotationSpec {
+    pub bits: u16,
+}
+
+impl Default for RotationSpec {
+    fn default() -> RotationSpec {
+        RotationSpec {
+            bits: RR_Rotate_0 as u16,
+        }
+    }
+}
+
+impl FromStr for RotationSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RotationSpec, String> {
+        let (degrees, reflect) = match s.split_once('+') {
+            Some((degrees, reflect)) => (degrees, Some(reflect)),
+            None => (s, None),
+        };
+
+        let mut bits = match degrees {
+            "0" => RR_Rotate_0 as u16,
+            "90" => RR_Rotate_90 as u16,
+            "180" => RR_Rotate_180 as u16,
+            "270" => RR_Rotate_270 as u16,
+            other => return Err(format!("invalid rotation {other:?}, expected 0/90/180/270")),
+        };
+
+        for axis in reflect.into_iter().flat_map(|r| r.chars()) {
+            bits |= match axis {
+                'x' => RR_Reflect_X as u16,
+                'y' => RR_Reflect_Y as u16,
+                other => return Err(format!("invalid reflect axis {other:?}, expected x or y")),
+            };
+        }
+
+        Ok(RotationSpec { bits })
+    }
+}
+
+/// Width/height, swapped if `rotation` turns the output on its side.
+///
+/// `rotation` is a bitmask that can carry `RR_Reflect_X`/`RR_Reflect_Y`
+/// alongside the `RR_Rotate_*` bits, so it must never be compared for
+/// equality against a single `RR_Rotate_90`/`RR_Rotate_270` value: a
+/// rotated-and-reflected request would fail that comparison, the swap
+/// would be skipped, and the subsequent set call would fail with
+/// BadMatch because the reported size no longer matches the mode. Test
+/// with `&` instead.
+pub fn swapped_dimensions(width: u32, height: u32, rotation: u16) -> (u32, u32) {
+    if rotation & (RR_Rotate_90 as u16 | RR_Rotate_270 as u16) != 0 {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// Check `rotation` (rotation bits plus any reflect bits) against the
+/// rotations the screen configuration actually reports as supported.
+pub fn validate_rotation(conf: *mut XRRScreenConfiguration, rotation: u16) -> io::Result<()> {
+    let mut current_rotation: u16 = 0;
+    let supported = unsafe { XRRConfigRotations(conf, &mut current_rotation) };
+
+    if rotation & !supported != 0 {
+        Err(io::ErrorKind::Unsupported.into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_parses_bare_degrees() {
+        assert_eq!("0".parse::<RotationSpec>().unwrap().bits, RR_Rotate_0 as u16);
+        assert_eq!("90".parse::<RotationSpec>().unwrap().bits, RR_Rotate_90 as u16);
+        assert_eq!("180".parse::<RotationSpec>().unwrap().bits, RR_Rotate_180 as u16);
+        assert_eq!("270".parse::<RotationSpec>().unwrap().bits, RR_Rotate_270 as u16);
+    }
+
+    #[test]
+    fn from_str_parses_reflect_suffixes() {
+        assert_eq!(
+            "90+x".parse::<RotationSpec>().unwrap().bits,
+            RR_Rotate_90 as u16 | RR_Reflect_X as u16
+        );
+        assert_eq!(
+            "90+y".parse::<RotationSpec>().unwrap().bits,
+            RR_Rotate_90 as u16 | RR_Reflect_Y as u16
+        );
+        assert_eq!(
+            "90+xy".parse::<RotationSpec>().unwrap().bits,
+            RR_Rotate_90 as u16 | RR_Reflect_X as u16 | RR_Reflect_Y as u16
+        );
+    }
+
+    #[test]
+    fn from_str_rejects_invalid_degrees() {
+        assert!("45".parse::<RotationSpec>().is_err());
+        assert!("".parse::<RotationSpec>().is_err());
+    }
+
+    #[test]
+    fn from_str_rejects_invalid_reflect_axis() {
+        assert!("90+z".parse::<RotationSpec>().is_err());
+    }
+
+    #[test]
+    fn swapped_dimensions_unrotated_keeps_order() {
+        assert_eq!(swapped_dimensions(1920, 1080, RR_Rotate_0 as u16), (1920, 1080));
+        assert_eq!(swapped_dimensions(1920, 1080, RR_Rotate_180 as u16), (1920, 1080));
+    }
+
+    #[test]
+    fn swapped_dimensions_swaps_on_90_and_270() {
+        assert_eq!(swapped_dimensions(1920, 1080, RR_Rotate_90 as u16), (1080, 1920));
+        assert_eq!(swapped_dimensions(1920, 1080, RR_Rotate_270 as u16), (1080, 1920));
+    }
+
+    #[test]
+    fn swapped_dimensions_swaps_when_rotation_also_carries_reflect_bits() {
+        let rotated_and_reflected = RR_Rotate_90 as u16 | RR_Reflect_X as u16;
+        assert_eq!(swapped_dimensions(1920, 1080, rotated_and_reflected), (1080, 1920));
+
+        let reflected_only = RR_Reflect_X as u16 | RR_Reflect_Y as u16;
+        assert_eq!(swapped_dimensions(1920, 1080, reflected_only), (1920, 1080));
+    }
+}
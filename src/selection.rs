@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::Mode;
+
+/// An explicit `WIDTHxHEIGHT` or `WIDTHxHEIGHT@RATE` request, as typed by
+/// a user on the command line.
+#[derive(Debug, Clone, Copy)]
+pub struct ExplicitMode {
+    pub width: u32,
+    pub height: u32,
+    pub rate: Option<f64>,
+}
+
+impl FromStr for ExplicitMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ExplicitMode, String> {
+        let (size, rate) = match s.split_once('@') {
+            Some((size, rate)) => (size, Some(rate)),
+            None => (s, None),
+        };
+
+        let (width, height) = size
+            .split_once('x')
+            .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {size:?}"))?;
+
+        let width: u32 = width
+            .parse()
+            .map_err(|_| format!("invalid width {width:?}"))?;
+        let height: u32 = height
+            .parse()
+            .map_err(|_| format!("invalid height {height:?}"))?;
+        let rate = rate
+            .map(|r| r.parse::<f64>().map_err(|_| format!("invalid rate {r:?}")))
+            .transpose()?;
+
+        Ok(ExplicitMode {
+            width,
+            height,
+            rate,
+        })
+    }
+}
+
+/// Coarse constraints a candidate mode must satisfy before the
+/// max-resolution heuristic picks among the survivors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModeFilter {
+    pub min_refresh: Option<f64>,
+    pub max_refresh: Option<f64>,
+    pub aspect_ratio: Option<(u32, u32)>,
+    pub exclude_interlaced: bool,
+}
+
+impl ModeFilter {
+    fn matches(&self, mode: &Mode) -> bool {
+        if self.exclude_interlaced && mode.interlaced {
+            return false;
+        }
+        if let Some(min) = self.min_refresh {
+            if mode.refresh_hz < min {
+                return false;
+            }
+        }
+        if let Some(max) = self.max_refresh {
+            if mode.refresh_hz > max {
+                return false;
+            }
+        }
+        if let Some((aw, ah)) = self.aspect_ratio {
+            if mode.width * ah != mode.height * aw {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// How to pick one [`Mode`] out of everything [`crate::list_modes`]
+/// reports.
+#[derive(Debug, Clone)]
+pub enum Policy {
+    /// Largest area, then highest refresh rate among ties. This is the
+    /// crate's original (and still default) behavior.
+    MaxResolution,
+    /// An exact `WIDTHxHEIGHT[@RATE]` request; without a rate, falls back
+    /// to the highest refresh rate at that size.
+    Explicit(ExplicitMode),
+    /// Narrow by [`ModeFilter`], then apply `MaxResolution` among the
+    /// survivors.
+    Filtered(ModeFilter),
+}
+
+impl Policy {
+    /// Pick a single mode out of `modes`, or `None` if nothing matches.
+    pub fn select(&self, modes: &[Mode]) -> Option<Mode> {
+        match self {
+            Policy::MaxResolution => max_resolution(modes),
+            Policy::Explicit(explicit) => select_explicit(modes, explicit),
+            Policy::Filtered(filter) => {
+                let survivors: Vec<Mode> =
+                    modes.iter().copied().filter(|m| filter.matches(m)).collect();
+                max_resolution(&survivors)
+            }
+        }
+    }
+}
+
+fn cmp_refresh(a: &Mode, b: &Mode) -> Ordering {
+    a.refresh_hz.partial_cmp(&b.refresh_hz).unwrap_or(Ordering::Equal)
+}
+
+fn max_resolution(modes: &[Mode]) -> Option<Mode> {
+    let max_area = modes.iter().map(|m| m.width * m.height).max()?;
+
+    modes
+        .iter()
+        .filter(|m| m.width * m.height == max_area)
+        .copied()
+        .max_by(cmp_refresh)
+}
+
+fn select_explicit(modes: &[Mode], explicit: &ExplicitMode) -> Option<Mode> {
+    let candidates: Vec<Mode> = modes
+        .iter()
+        .copied()
+        .filter(|m| m.width == explicit.width && m.height == explicit.height)
+        .collect();
+
+    match explicit.rate {
+        Some(rate) => candidates
+            .into_iter()
+            .min_by(|a, b| {
+                (a.refresh_hz - rate)
+                    .abs()
+                    .partial_cmp(&(b.refresh_hz - rate).abs())
+                    .unwrap_or(Ordering::Equal)
+            }),
+        None => max_resolution(&candidates),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(width: u32, height: u32, refresh_hz: f64, interlaced: bool) -> Mode {
+        Mode {
+            width,
+            height,
+            refresh_hz,
+            interlaced,
+            size_index: 0,
+            mode_id: 0,
+        }
+    }
+
+    #[test]
+    fn explicit_mode_parses_size_only() {
+        let parsed: ExplicitMode = "1920x1080".parse().unwrap();
+        assert_eq!(parsed.width, 1920);
+        assert_eq!(parsed.height, 1080);
+        assert_eq!(parsed.rate, None);
+    }
+
+    #[test]
+    fn explicit_mode_parses_size_and_rate() {
+        let parsed: ExplicitMode = "1920x1080@59.94".parse().unwrap();
+        assert_eq!(parsed.width, 1920);
+        assert_eq!(parsed.height, 1080);
+        assert_eq!(parsed.rate, Some(59.94));
+    }
+
+    #[test]
+    fn explicit_mode_rejects_missing_separator() {
+        assert!("1920".parse::<ExplicitMode>().is_err());
+    }
+
+    #[test]
+    fn explicit_mode_rejects_non_numeric_fields() {
+        assert!("widexhigh".parse::<ExplicitMode>().is_err());
+        assert!("1920x1080@fast".parse::<ExplicitMode>().is_err());
+    }
+
+    #[test]
+    fn mode_filter_excludes_interlaced() {
+        let filter = ModeFilter {
+            exclude_interlaced: true,
+            ..ModeFilter::default()
+        };
+        assert!(!filter.matches(&mode(1920, 1080, 60.0, true)));
+        assert!(filter.matches(&mode(1920, 1080, 60.0, false)));
+    }
+
+    #[test]
+    fn mode_filter_refresh_bounds_are_inclusive_ranges() {
+        let filter = ModeFilter {
+            min_refresh: Some(50.0),
+            max_refresh: Some(60.0),
+            ..ModeFilter::default()
+        };
+        assert!(filter.matches(&mode(1920, 1080, 50.0, false)));
+        assert!(filter.matches(&mode(1920, 1080, 60.0, false)));
+        assert!(!filter.matches(&mode(1920, 1080, 49.9, false)));
+        assert!(!filter.matches(&mode(1920, 1080, 60.1, false)));
+    }
+
+    #[test]
+    fn mode_filter_aspect_ratio_rejects_mismatches() {
+        let filter = ModeFilter {
+            aspect_ratio: Some((16, 9)),
+            ..ModeFilter::default()
+        };
+        assert!(filter.matches(&mode(1920, 1080, 60.0, false)));
+        assert!(!filter.matches(&mode(1024, 768, 60.0, false)));
+    }
+
+    #[test]
+    fn policy_max_resolution_breaks_ties_on_refresh_rate() {
+        let modes = [
+            mode(1920, 1080, 60.0, false),
+            mode(1920, 1080, 144.0, false),
+            mode(1280, 720, 240.0, false),
+        ];
+
+        let selected = Policy::MaxResolution.select(&modes).unwrap();
+        assert_eq!((selected.width, selected.height), (1920, 1080));
+        assert_eq!(selected.refresh_hz, 144.0);
+    }
+
+    #[test]
+    fn policy_explicit_without_rate_falls_back_to_max_resolution() {
+        let modes = [
+            mode(1920, 1080, 60.0, false),
+            mode(1920, 1080, 144.0, false),
+        ];
+        let explicit = ExplicitMode {
+            width: 1920,
+            height: 1080,
+            rate: None,
+        };
+
+        let selected = Policy::Explicit(explicit).select(&modes).unwrap();
+        assert_eq!(selected.refresh_hz, 144.0);
+    }
+
+    #[test]
+    fn policy_explicit_with_rate_picks_closest_match() {
+        let modes = [
+            mode(1920, 1080, 59.94, false),
+            mode(1920, 1080, 60.0, false),
+            mode(1920, 1080, 144.0, false),
+        ];
+        let explicit = ExplicitMode {
+            width: 1920,
+            height: 1080,
+            rate: Some(60.05),
+        };
+
+        let selected = Policy::Explicit(explicit).select(&modes).unwrap();
+        assert_eq!(selected.refresh_hz, 60.0);
+    }
+
+    #[test]
+    fn policy_filtered_narrows_before_max_resolution() {
+        let modes = [
+            mode(1920, 1080, 60.0, true),
+            mode(1920, 1080, 144.0, false),
+            mode(3840, 2160, 30.0, false),
+        ];
+        let filter = ModeFilter {
+            exclude_interlaced: true,
+            max_refresh: Some(150.0),
+            ..ModeFilter::default()
+        };
+
+        let selected = Policy::Filtered(filter).select(&modes).unwrap();
+        assert_eq!((selected.width, selected.height), (3840, 2160));
+    }
+}